@@ -1,5 +1,6 @@
 use regex::Regex;
 use reqwest::{self, header};
+use sha2::{Digest, Sha256};
 use std::boxed::Box;
 use std::fs;
 use std::io::{self, Write};
@@ -9,6 +10,7 @@ pub struct Download<'a> {
   url: String,
   headers: reqwest::header::HeaderMap,
   on_progress: Option<Box<dyn Fn(f64) + 'a>>,
+  expected_sha256: Option<String>,
 }
 impl<'a> Download<'a> {
   /// Specify download url
@@ -17,9 +19,19 @@ impl<'a> Download<'a> {
       url,
       headers: reqwest::header::HeaderMap::new(),
       on_progress: None,
+      expected_sha256: None,
     }
   }
 
+  /// Require the downloaded file's SHA-256 digest to match `hex_digest`
+  /// (a lowercase or uppercase hex-encoded digest). `download_to` hashes
+  /// the bytes as they're written to disk and deletes the partial file if
+  /// the digest doesn't match.
+  pub fn expected_sha256(&mut self, hex_digest: String) -> &mut Self {
+    self.expected_sha256 = Some(hex_digest.to_lowercase());
+    self
+  }
+
   /// Set the download request headers
   pub fn set_headers(&mut self, headers: reqwest::header::HeaderMap) -> &mut Self {
     self.headers = headers;
@@ -90,9 +102,11 @@ impl<'a> Download<'a> {
 
           let mut src = io::BufReader::new(resp);
           let mut downloaded = 0;
+          let mut hasher = Sha256::new();
           loop {
             let n = {
               let buf = src.fill_buf()?;
+              hasher.update(&buf);
               dest.write_all(&buf)?;
               buf.len()
             };
@@ -107,6 +121,20 @@ impl<'a> Download<'a> {
 
             // TODO send downloaded as progress
           }
+
+          if let Some(ref expected_sha256) = self.expected_sha256 {
+            let digest = finalize_hex(hasher);
+            if &digest != expected_sha256 {
+              fs::remove_file(&dest_path)?;
+              bail!(
+                crate::ErrorKind::Download,
+                "sha256 mismatch: expected {}, got {}",
+                expected_sha256,
+                digest
+              )
+            }
+          }
+
           Ok((filename.to_string(), dest_path))
         }
         None => bail!(
@@ -122,4 +150,234 @@ impl<'a> Download<'a> {
       )
     }
   }
+
+  /// Async version of `download_to`, streaming the response instead of
+  /// blocking the calling thread, and resumable: if `dest_dir` already has
+  /// a partial download for this URL, it's continued with a
+  /// `Range: bytes=<downloaded>-` request instead of restarted from zero.
+  /// Falls back to a full download if the server doesn't honor the range
+  /// request (i.e. it replies `200 OK` instead of `206 Partial Content`).
+  ///
+  /// The destination filename is taken from the URL itself (with any query
+  /// string stripped) rather than a `Content-Disposition` header, since
+  /// that header isn't available until after the (possibly ranged) request
+  /// is sent, and by then the destination path is already needed to check
+  /// for a resumable partial download.
+  ///
+  /// * Errors:
+  ///     * `reqwest` network errors
+  ///     * Unsuccessful response status
+  ///     * Reading from the response stream
+  ///     * Writing to the destination file
+  pub async fn download_to_async(self, dest_dir: &Path) -> crate::Result<(String, PathBuf)> {
+    use futures::StreamExt;
+
+    let filename = filename_from_url(&self.url);
+    let dest_path = dest_dir.join(&filename);
+
+    let mut downloaded = match fs::metadata(&dest_path) {
+      Ok(meta) => meta.len(),
+      Err(_) => 0,
+    };
+
+    let mut headers = self.headers.clone();
+    if !headers.contains_key(header::USER_AGENT) {
+      headers.insert(
+        header::USER_AGENT,
+        "tauri/self-update".parse().expect("invalid user-agent"),
+      );
+    }
+    if downloaded > 0 {
+      headers.insert(
+        header::RANGE,
+        format!("bytes={}-", downloaded)
+          .parse()
+          .expect("invalid range header"),
+      );
+    }
+
+    set_ssl_vars!();
+    let resp = reqwest::Client::new()
+      .get(&self.url)
+      .headers(headers)
+      .send()
+      .await?;
+
+    if !resp.status().is_success() {
+      bail!(
+        crate::ErrorKind::Download,
+        "Download request failed with status: {:?}",
+        resp.status()
+      )
+    }
+
+    let resumed = is_resumed(downloaded, resp.status());
+    if downloaded > 0 && !resumed {
+      downloaded = 0;
+    }
+
+    let total_size = total_size_from_headers(resp.headers(), downloaded);
+
+    let mut open_options = fs::OpenOptions::new();
+    open_options.create(true).write(true);
+    if resumed {
+      open_options.append(true);
+    } else {
+      open_options.truncate(true);
+    }
+    let mut dest = open_options.open(&dest_path)?;
+
+    let mut stream = resp.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+      let chunk = chunk?;
+      dest.write_all(&chunk)?;
+      downloaded += chunk.len() as u64;
+      if let Some(on_progress) = &self.on_progress {
+        if total_size > 0 {
+          on_progress(downloaded as f64 / total_size as f64);
+        }
+      }
+    }
+
+    if let Some(ref expected_sha256) = self.expected_sha256 {
+      // Hash the whole file: a resumed download only streamed its tail.
+      let mut hasher = Sha256::new();
+      hasher.update(&fs::read(&dest_path)?);
+      let digest = finalize_hex(hasher);
+      if &digest != expected_sha256 {
+        fs::remove_file(&dest_path)?;
+        bail!(
+          crate::ErrorKind::Download,
+          "sha256 mismatch: expected {}, got {}",
+          expected_sha256,
+          digest
+        )
+      }
+    }
+
+    Ok((filename, dest_path))
+  }
+}
+
+/// The total size of the resource being downloaded: the `Content-Range`
+/// total if the response has one, otherwise `Content-Length` plus the
+/// `downloaded` bytes already on disk (zero when starting fresh).
+fn total_size_from_headers(headers: &reqwest::header::HeaderMap, downloaded: u64) -> u64 {
+  let content_range_total = headers
+    .get(header::CONTENT_RANGE)
+    .and_then(|v| v.to_str().ok())
+    .and_then(|s| s.rsplit('/').next())
+    .and_then(|total| total.parse::<u64>().ok());
+  if let Some(total) = content_range_total {
+    return total;
+  }
+
+  headers
+    .get(header::CONTENT_LENGTH)
+    .and_then(|v| v.to_str().ok())
+    .and_then(|s| s.parse::<u64>().ok())
+    .map(|len| len + downloaded)
+    .unwrap_or(0)
+}
+
+/// A destination filename for `download_to_async`, taken from the last
+/// path segment of `url` with any query string or fragment stripped.
+/// Without this, a signed URL (S3/GCS/Azure SAS, a GitHub asset redirect)
+/// would leave the query string stuck on the end of the filename, breaking
+/// `is_download_installable`/`is_download_valid`'s extension checks.
+fn filename_from_url(url: &str) -> String {
+  url
+    .split(['?', '#'])
+    .next()
+    .unwrap_or(url)
+    .rsplit('/')
+    .next()
+    .filter(|s| !s.is_empty())
+    .unwrap_or("download")
+    .to_string()
+}
+
+/// Whether a ranged download actually resumed: there was something on disk
+/// to resume *and* the server honored the `Range` request with a `206`,
+/// rather than ignoring it and sending the whole file again with `200`.
+fn is_resumed(downloaded: u64, status: reqwest::StatusCode) -> bool {
+  downloaded > 0 && status == reqwest::StatusCode::PARTIAL_CONTENT
+}
+
+/// Hex-encode a finalized SHA-256 digest, matching the lowercase format
+/// `expected_sha256` is normalized to.
+fn finalize_hex(hasher: Sha256) -> String {
+  format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn filename_from_url_strips_query_string() {
+    assert_eq!(
+      filename_from_url("https://bucket.s3.amazonaws.com/app-1.2.3.tar.gz?X-Amz-Signature=abc123&X-Amz-Expires=900"),
+      "app-1.2.3.tar.gz"
+    );
+  }
+
+  #[test]
+  fn filename_from_url_strips_fragment() {
+    assert_eq!(
+      filename_from_url("https://example.com/releases/app.tar.gz#sha256=deadbeef"),
+      "app.tar.gz"
+    );
+  }
+
+  #[test]
+  fn filename_from_url_with_no_query_is_unchanged() {
+    assert_eq!(
+      filename_from_url("https://example.com/releases/app-1.2.3.tar.gz"),
+      "app-1.2.3.tar.gz"
+    );
+  }
+
+  #[test]
+  fn filename_from_url_falls_back_for_an_empty_path() {
+    assert_eq!(filename_from_url("https://example.com/"), "download");
+  }
+
+  #[test]
+  fn is_resumed_requires_existing_bytes_and_partial_content() {
+    assert!(is_resumed(100, reqwest::StatusCode::PARTIAL_CONTENT));
+    assert!(!is_resumed(0, reqwest::StatusCode::PARTIAL_CONTENT));
+    assert!(!is_resumed(100, reqwest::StatusCode::OK));
+  }
+
+  #[test]
+  fn finalize_hex_matches_a_known_sha256_digest() {
+    let mut hasher = Sha256::new();
+    hasher.update(b"abc");
+    assert_eq!(
+      finalize_hex(hasher),
+      "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+  }
+
+  #[test]
+  fn total_size_from_headers_prefers_content_range_total() {
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(header::CONTENT_RANGE, "bytes 500-999/2000".parse().unwrap());
+    headers.insert(header::CONTENT_LENGTH, "500".parse().unwrap());
+    assert_eq!(total_size_from_headers(&headers, 500), 2000);
+  }
+
+  #[test]
+  fn total_size_from_headers_adds_content_length_to_what_was_downloaded() {
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(header::CONTENT_LENGTH, "1500".parse().unwrap());
+    assert_eq!(total_size_from_headers(&headers, 500), 2000);
+  }
+
+  #[test]
+  fn total_size_from_headers_is_zero_with_no_size_headers() {
+    let headers = reqwest::header::HeaderMap::new();
+    assert_eq!(total_size_from_headers(&headers, 0), 0);
+  }
 }
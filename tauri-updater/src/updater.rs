@@ -7,7 +7,11 @@ use crate::http;
 use tauri_api::file::{Extract, Move};
 
 mod backend;
+mod object_storage;
+mod signature;
+mod target;
 pub use backend::Backend;
+pub use object_storage::{Endpoint, ObjectStorageBackend};
 
 /// Status returned after updating
 ///
@@ -16,6 +20,10 @@ pub use backend::Backend;
 pub enum Status {
   UpToDate(String),
   Updated(String),
+  /// A newer release exists but wasn't installed, because the
+  /// `UpdatePolicy` only auto-applies critical releases and this one
+  /// isn't marked critical.
+  UpdateAvailable(Release),
 }
 impl Status {
   /// Return the version tag
@@ -24,6 +32,7 @@ impl Status {
     match *self {
       UpToDate(ref s) => s,
       Updated(ref s) => s,
+      UpdateAvailable(ref release) => &release.version,
     }
   }
 
@@ -42,6 +51,41 @@ impl Status {
       _ => false,
     }
   }
+
+  /// Returns `true` if `Status::UpdateAvailable`
+  pub fn update_available(&self) -> bool {
+    match *self {
+      Status::UpdateAvailable(_) => true,
+      _ => false,
+    }
+  }
+}
+
+/// A release channel/track, used to keep users on the build they opted
+/// into (e.g. so a beta tester doesn't get silently moved to nightly).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+  Stable,
+  Beta,
+  Nightly,
+}
+
+/// Controls whether `Updater::update` installs a newer release outright or
+/// merely reports that one exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdatePolicy {
+  /// Install any newer release as soon as one is found.
+  AlwaysInstall,
+  /// Only auto-install releases marked `Release::is_critical`; other
+  /// releases are surfaced as `Status::UpdateAvailable` so the app can
+  /// decide when (or whether) to prompt the user.
+  CriticalOnly,
+}
+
+impl Default for UpdatePolicy {
+  fn default() -> Self {
+    UpdatePolicy::AlwaysInstall
+  }
 }
 
 #[derive(Clone, Debug)]
@@ -49,6 +93,53 @@ pub struct Release {
   pub version: String,
   pub asset_name: String,
   pub download_url: String,
+  /// Per-platform assets, when the backend hosts more than one build of
+  /// the same release. Empty for backends that only ever serve a single
+  /// asset (via `asset_name`/`download_url` above).
+  pub assets: Vec<Asset>,
+  /// Which release track this build was published on.
+  pub channel: Channel,
+  /// Whether `UpdatePolicy::CriticalOnly` should auto-install this release
+  /// rather than just reporting it via `Status::UpdateAvailable`.
+  pub is_critical: bool,
+}
+
+/// A single platform-specific build of a `Release`.
+#[derive(Clone, Debug)]
+pub struct Asset {
+  pub platform_match: PlatformMatch,
+  pub url: String,
+  pub name: String,
+}
+
+/// The `(os, arch)` pair an `Asset` was built for, using the same names as
+/// `std::env::consts::OS`/`ARCH` (e.g. `"linux"`/`"x86_64"`).
+#[derive(Clone, Debug)]
+pub struct PlatformMatch {
+  pub os: String,
+  pub arch: String,
+}
+
+/// Pick the first asset in `release` whose `platform_match` matches the
+/// currently running platform.
+///
+/// * Errors:
+///     * Updater - No asset matches the detected `(os, arch)`
+pub fn select_asset(release: &Release) -> crate::Result<&Asset> {
+  let (os, arch) = target::current_os_arch();
+  release
+    .assets
+    .iter()
+    .find(|asset| asset.platform_match.os == os && asset.platform_match.arch == arch)
+    .ok_or_else(|| {
+      crate::ErrorKind::Updater(format!(
+        "no release asset found for target {} ({}/{})",
+        target::current_target(),
+        os,
+        arch
+      ))
+      .into()
+    })
 }
 
 #[derive(Default)]
@@ -57,6 +148,9 @@ pub struct UpdaterBuilder {
   current_version: Option<String>,
   on_progress: Option<Box<dyn Fn(f64)>>,
   backend: Option<Box<dyn Backend>>,
+  pubkey: Option<String>,
+  channel: Option<Channel>,
+  policy: UpdatePolicy,
 }
 
 impl UpdaterBuilder {
@@ -88,6 +182,30 @@ impl UpdaterBuilder {
     self
   }
 
+  /// Set the base64-encoded minisign public key used to verify downloaded
+  /// releases. When set, `Updater::update` fetches the backend's
+  /// `signature` for the release and refuses to install the download if it
+  /// doesn't verify.
+  pub fn pubkey(mut self, pubkey: &str) -> Self {
+    self.pubkey = Some(pubkey.to_owned());
+    self
+  }
+
+  /// Only consider releases published on `channel`, ignoring the backend's
+  /// latest release if it's on a different track.
+  pub fn channel(mut self, channel: Channel) -> Self {
+    self.channel = Some(channel);
+    self
+  }
+
+  /// Set the policy controlling whether a newer release is installed
+  /// outright or just reported via `Status::UpdateAvailable`. Defaults to
+  /// `UpdatePolicy::AlwaysInstall`.
+  pub fn policy(mut self, policy: UpdatePolicy) -> Self {
+    self.policy = policy;
+    self
+  }
+
   /// Confirm config and create a ready-to-use `Updater`
   ///
   /// * Errors:
@@ -110,6 +228,9 @@ impl UpdaterBuilder {
       } else {
         bail!(crate::ErrorKind::Config, "`backend` required")
       },
+      pubkey: self.pubkey,
+      channel: self.channel,
+      policy: self.policy,
     })
   }
 }
@@ -120,6 +241,9 @@ pub struct Updater {
   current_version: String,
   on_progress: Option<Box<dyn Fn(f64)>>,
   backend: Box<dyn Backend>,
+  pubkey: Option<String>,
+  channel: Option<Channel>,
+  policy: UpdatePolicy,
 }
 
 impl Updater {
@@ -136,18 +260,49 @@ impl Updater {
     }
   }
 
-  pub fn update(self) -> crate::Result<Status> {
+  /// Check the backend and, unless there's nothing to do (or the policy
+  /// says not to install yet), work out the download url and a temp dir
+  /// to download into. Shared by the sync and async `update*` entry
+  /// points, which only differ in how they drive the actual download.
+  fn prepare(&self) -> crate::Result<PreparedUpdate> {
     self.println(&format!(
       "Checking current version... v{}",
       self.current_version
     ));
 
-    if self.backend.is_uptodate(self.current_version.clone())? {
-      return Ok(Status::UpToDate(self.current_version.clone()));
+    // Fetch `latest_release` at most once: backends like
+    // `ObjectStorageBackend` list an entire bucket to answer it, and both
+    // the up-to-date check and the channel/policy filtering below need it.
+    let latest = self.backend.latest_release().ok();
+
+    let is_uptodate = match latest {
+      Some(ref latest) => backend::version_is_uptodate(&self.current_version, &latest.version)?,
+      None => self.backend.is_uptodate(self.current_version.clone())?,
+    };
+    if is_uptodate {
+      return Ok(PreparedUpdate::AlreadyDone(Status::UpToDate(
+        self.current_version.clone(),
+      )));
+    }
+
+    if let Some(latest) = latest {
+      if let Some(channel) = self.channel {
+        if latest.channel != channel {
+          return Ok(PreparedUpdate::AlreadyDone(Status::UpToDate(
+            self.current_version.clone(),
+          )));
+        }
+      }
+      if self.policy == UpdatePolicy::CriticalOnly && !latest.is_critical {
+        return Ok(PreparedUpdate::AlreadyDone(Status::UpdateAvailable(latest)));
+      }
     }
 
     let bin_install_path = env::current_exe()?;
-    let download_url = self.backend.update_url(self.current_version.clone())?;
+    let download_url = match self.backend.release(self.current_version.clone()) {
+      Ok(release) => select_asset(&release)?.url.clone(),
+      Err(_) => self.backend.update_url(self.current_version.clone())?,
+    };
 
     if cfg!(debug_assertions) {
       println!("\n{} release status:", self.bin_name);
@@ -164,17 +319,38 @@ impl Updater {
     let tmp_dir =
       tempdir::TempDir::new_in(&tmp_dir_parent, &format!("{}_download", self.bin_name))?;
 
-    self.println("Downloading...");
-    let mut downloader = http::Download::from_url(download_url.clone());
-    if let Some(ref on_progress) = self.on_progress {
-      downloader.on_progress(on_progress);
+    Ok(PreparedUpdate::ReadyToDownload {
+      download_url,
+      tmp_dir,
+    })
+  }
+
+  /// Verify (if configured) and install a completed download. Shared by
+  /// the sync and async `update*` entry points.
+  fn verify_and_install(
+    &self,
+    tmp_dir: &tempdir::TempDir,
+    filename: String,
+    downloaded_path: PathBuf,
+  ) -> crate::Result<()> {
+    if let Some(ref pubkey) = self.pubkey {
+      self.print_flush("Verifying signature... ")?;
+      let sig = self
+        .backend
+        .signature(self.current_version.clone())
+        .map_err(|e| crate::ErrorKind::Updater(format!("failed to fetch signature: {}", e)))?;
+      let public_key = signature::PublicKey::decode(pubkey)
+        .map_err(|e| crate::ErrorKind::Updater(format!("invalid public key: {}", e)))?;
+      let downloaded_bytes = std::fs::read(&downloaded_path)?;
+      signature::verify(&public_key, &sig, &downloaded_bytes)
+        .map_err(|e| crate::ErrorKind::Updater(format!("signature verification failed: {}", e)))?;
+      self.println("valid");
     }
 
-    let (filename, downloaded_path) = downloader.download_to(&tmp_dir.path())?;
     if is_download_installable(filename.clone()) {
-      install_update(downloaded_path)?;
+      install_update(downloaded_path, true)?;
     } else if is_download_valid(downloaded_path.clone()) {
-       self.print_flush("Extracting archive... ")?;
+      self.print_flush("Extracting archive... ")?;
       let extract_path = tmp_dir.path().join("extracted");
       Extract::from_source(&downloaded_path).extract_into(&extract_path)?;
       let entries = std::fs::read_dir(extract_path)?
@@ -182,41 +358,244 @@ impl Updater {
         .collect::<Result<Vec<_>, std::io::Error>>()?;
       match entries.first() {
         Some(entry) => {
-          install_update(entry.to_path_buf())?;
-        },
+          install_update(entry.to_path_buf(), false)?;
+        }
         None => {
-          bail!(
-            crate::ErrorKind::Updater,
-            "can't read extracted dir"
-          )
+          bail!(crate::ErrorKind::Updater, "can't read extracted dir")
         }
       }
     } else {
-      bail!(
-        crate::ErrorKind::Updater,
-        "invalid file {}",
-        filename
-      )
+      bail!(crate::ErrorKind::Updater, "invalid file {}", filename)
     }
 
+    Ok(())
+  }
+
+  pub fn update(self) -> crate::Result<Status> {
+    let (download_url, tmp_dir) = match self.prepare()? {
+      PreparedUpdate::AlreadyDone(status) => return Ok(status),
+      PreparedUpdate::ReadyToDownload {
+        download_url,
+        tmp_dir,
+      } => (download_url, tmp_dir),
+    };
+
+    self.println("Downloading...");
+    let mut downloader = http::Download::from_url(download_url);
+    if let Some(ref on_progress) = self.on_progress {
+      downloader.on_progress(on_progress);
+    }
+    if let Ok(expected_sha256) = self.backend.sha256(self.current_version.clone()) {
+      downloader.expected_sha256(expected_sha256);
+    }
+
+    let (filename, downloaded_path) = downloader.download_to(&tmp_dir.path())?;
+    self.verify_and_install(&tmp_dir, filename, downloaded_path)?;
+
+    self.println("Done");
+    Ok(Status::Updated(self.current_version))
+  }
+
+  /// Async counterpart to `update`, streaming the download via
+  /// `http::Download::download_to_async` instead of blocking the calling
+  /// thread, with resumable range requests if a previous attempt was
+  /// interrupted. Everything else (channel/policy checks, signature and
+  /// sha256 verification, install) is identical to `update`.
+  pub async fn update_async(self) -> crate::Result<Status> {
+    let (download_url, tmp_dir) = match self.prepare()? {
+      PreparedUpdate::AlreadyDone(status) => return Ok(status),
+      PreparedUpdate::ReadyToDownload {
+        download_url,
+        tmp_dir,
+      } => (download_url, tmp_dir),
+    };
+
+    self.println("Downloading...");
+    let mut downloader = http::Download::from_url(download_url);
+    if let Some(ref on_progress) = self.on_progress {
+      downloader.on_progress(on_progress);
+    }
+    if let Ok(expected_sha256) = self.backend.sha256(self.current_version.clone()) {
+      downloader.expected_sha256(expected_sha256);
+    }
+
+    let (filename, downloaded_path) = downloader.download_to_async(&tmp_dir.path()).await?;
+    self.verify_and_install(&tmp_dir, filename, downloaded_path)?;
+
     self.println("Done");
     Ok(Status::Updated(self.current_version))
   }
 }
 
+/// Outcome of `Updater::prepare`: either there's nothing further to do, or
+/// a download is ready to be driven (synchronously or asynchronously).
+enum PreparedUpdate {
+  AlreadyDone(Status),
+  ReadyToDownload {
+    download_url: String,
+    tmp_dir: tempdir::TempDir,
+  },
+}
+
+/// Swap `target` (the running exe or app bundle) for `replacement` by
+/// renaming `target` aside and moving `replacement` into its place. The
+/// backup is kept until the move succeeds so a failed install can be
+/// rolled back instead of leaving the app missing its binary.
+fn replace_in_place(target: &std::path::Path, replacement: &std::path::Path) -> crate::Result<()> {
+  let backup_path = target.with_extension("old");
+  std::fs::rename(target, &backup_path)?;
+  if let Err(e) = Move::from_source(replacement).to_dest(target) {
+    let _ = std::fs::rename(&backup_path, target);
+    bail!(
+      crate::ErrorKind::Updater,
+      "failed to install update: {}",
+      e
+    )
+  }
+  let _ = if backup_path.is_dir() {
+    std::fs::remove_dir_all(&backup_path)
+  } else {
+    std::fs::remove_file(&backup_path)
+  };
+  Ok(())
+}
+
+/// Windows keeps a running exe's image locked against rename/replace/delete
+/// (barring non-default delete-sharing configurations), so `replace_in_place`
+/// can't be used on the current process's own binary the way it can on
+/// Unix or for a macOS `.app` bundle. Instead, write out a tiny detached
+/// helper script that waits for this process to exit and only then swaps
+/// the new binary into place, the same trick most native Windows
+/// self-updaters use.
+///
+/// Unlike `replace_in_place`, the script runs after this process (and any
+/// Rust error reporting) has already exited, so it can't bail out and
+/// report a failure the normal way. Instead it renames the old exe aside
+/// rather than deleting it, restores it if the `move` of the new exe
+/// fails, and leaves a `.update.log` marker next to the target recording
+/// which branch it took, so the app can check on its next launch whether
+/// the previous update actually succeeded.
 #[cfg(windows)]
-fn install_update(path: PathBuf) -> crate::Result<()> {
+fn spawn_self_replace_helper(target: &std::path::Path, replacement: &std::path::Path) -> crate::Result<()> {
+  use std::process::Command;
+
+  let script_path = target.with_extension("update.bat");
+  let backup_path = target.with_extension("update.old");
+  let log_path = target.with_extension("update.log");
+  let script = format!(
+    "@echo off\r\n\
+     :wait\r\n\
+     move /y \"{target}\" \"{backup}\" >nul 2>&1\r\n\
+     if exist \"{target}\" (\r\n\
+     \tping -n 2 127.0.0.1 >nul\r\n\
+     \tgoto wait\r\n\
+     )\r\n\
+     move /y \"{replacement}\" \"{target}\" >nul\r\n\
+     if exist \"{target}\" (\r\n\
+     \tdel /f /q \"{backup}\" >nul 2>&1\r\n\
+     \techo ok > \"{log}\"\r\n\
+     ) else (\r\n\
+     \tmove /y \"{backup}\" \"{target}\" >nul\r\n\
+     \techo failed: move of new exe did not land, restored backup > \"{log}\"\r\n\
+     )\r\n\
+     del /f /q \"%~f0\"\r\n",
+    target = target.display(),
+    backup = backup_path.display(),
+    replacement = replacement.display(),
+    log = log_path.display(),
+  );
+  std::fs::write(&script_path, script)?;
+
+  Command::new("cmd")
+    .args(&["/C", "start", "", "/min", &script_path.to_string_lossy()])
+    .spawn()?;
   Ok(())
 }
 
+/// Check the marker left by `spawn_self_replace_helper`'s detached script,
+/// if any, reporting whether the last self-replace on Windows actually
+/// succeeded. The script can't report failure itself (the process that
+/// would report it has already exited by the time it runs), so apps that
+/// need to know should call this once on startup.
+#[cfg(windows)]
+pub fn last_self_replace_failed() -> crate::Result<Option<String>> {
+  let log_path = env::current_exe()?.with_extension("update.log");
+  let contents = match std::fs::read_to_string(&log_path) {
+    Ok(contents) => contents,
+    Err(_) => return Ok(None),
+  };
+  std::fs::remove_file(&log_path)?;
+  let contents = contents.trim();
+  if contents == "ok" {
+    Ok(None)
+  } else {
+    Ok(Some(contents.to_string()))
+  }
+}
+
+#[cfg(windows)]
+fn install_update(path: PathBuf, is_installer: bool) -> crate::Result<()> {
+  use std::process::Command;
+
+  let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+  if is_installer {
+    match ext {
+      "msi" => {
+        Command::new("msiexec")
+          .args(&["/i", &path.to_string_lossy()])
+          .spawn()?;
+      }
+      _ => {
+        Command::new(&path).spawn()?;
+      }
+    }
+    // The installer takes over from here; let it replace us once we exit.
+    std::process::exit(0);
+  }
+
+  // Can't rename/replace our own running image directly on Windows; hand
+  // off to a detached helper and exit so it can finish the swap once the
+  // lock on the exe is released.
+  spawn_self_replace_helper(&env::current_exe()?, &path)?;
+  std::process::exit(0);
+}
+
 #[cfg(all(unix, not(target_os = "macos")))]
-fn install_update(path: PathBuf) -> crate::Result<()> {
-  Ok(())
+fn install_update(path: PathBuf, is_installer: bool) -> crate::Result<()> {
+  if is_installer {
+    let status = std::process::Command::new("dpkg")
+      .arg("-i")
+      .arg(&path)
+      .status()?;
+    if !status.success() {
+      bail!(
+        crate::ErrorKind::Updater,
+        "dpkg -i {:?} exited with {}",
+        path,
+        status
+      )
+    }
+    return Ok(());
+  }
+
+  // No package to hand off to, so self-replace: the running binary can't
+  // be overwritten directly, but it can be renamed out of the way and a
+  // new file moved into its place.
+  replace_in_place(&env::current_exe()?, &path)
 }
 
 #[cfg(target_os = "macos")]
-fn install_update(path: PathBuf) -> crate::Result<()> {
-  Ok(())
+fn install_update(path: PathBuf, _is_installer: bool) -> crate::Result<()> {
+  let current_exe = env::current_exe()?;
+  let app_bundle = current_exe
+    .ancestors()
+    .find(|p| p.extension().map(|ext| ext == "app").unwrap_or(false))
+    .ok_or_else(|| {
+      crate::ErrorKind::Updater("could not locate the running .app bundle".into())
+    })?
+    .to_path_buf();
+
+  replace_in_place(&app_bundle, &path)
 }
 
 fn is_download_installable(filename: String) -> bool {
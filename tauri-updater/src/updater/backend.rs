@@ -1,4 +1,68 @@
+use super::Release;
+
 pub trait Backend {
-  fn is_uptodate(&self, version: String) -> Result<bool, String>;
   fn update_url(&self, version: String) -> Result<String, String>;
+
+  /// Report whether `version` is already the latest available release.
+  ///
+  /// The default implementation delegates to `latest_release` and compares
+  /// versions with semver, which is almost always what you want; override
+  /// this directly only if your backend needs something other than strict
+  /// semver comparison (e.g. a `latest` alias that doesn't parse as semver).
+  fn is_uptodate(&self, version: String) -> Result<bool, String> {
+    let latest = self.latest_release()?;
+    version_is_uptodate(&version, &latest.version)
+  }
+
+  /// Return the latest release available from this backend. This powers
+  /// the default `is_uptodate` above, which does the semver comparison so
+  /// individual backends don't each have to get it right.
+  fn latest_release(&self) -> Result<Release, String> {
+    Err("this backend does not implement latest_release".into())
+  }
+
+  /// Return the full `Release` to update to, including any per-platform
+  /// `assets`. `version` is the running app's current version, as with
+  /// `update_url`. When this returns `Ok`, `Updater::update` prefers the asset
+  /// matching the running target over the single `update_url` above. The
+  /// default implementation reports that it isn't available, keeping
+  /// single-asset backends working unchanged.
+  fn release(&self, _version: String) -> Result<Release, String> {
+    Err("this backend does not expose per-target release assets".into())
+  }
+
+  /// Return the detached minisign signature (the contents of the
+  /// `.minisig` file) for the release the updater downloads, if the backend
+  /// serves signed releases; `version` is the running app's current
+  /// version. The default implementation reports that signatures aren't
+  /// available, so an `Updater` built with a `pubkey` fails the update.
+  fn signature(&self, _version: String) -> Result<String, String> {
+    Err("this backend does not provide release signatures".into())
+  }
+
+  /// Return the expected SHA-256 digest (lowercase hex) of the release
+  /// asset the updater downloads, if the backend publishes one; `version`
+  /// is the running app's current version. The default
+  /// implementation reports that no digest is available, which skips
+  /// verification.
+  fn sha256(&self, _version: String) -> Result<String, String> {
+    Err("this backend does not provide a sha256 digest".into())
+  }
+}
+
+/// Compare `current` against `latest` with semver, tolerating the `v` tag
+/// prefix. Shared by the default `Backend::is_uptodate` above and by
+/// `Updater::prepare`, which needs the same up-to-date check but already has
+/// the `Release` in hand and shouldn't fetch it a second time.
+pub(super) fn version_is_uptodate(current: &str, latest: &str) -> Result<bool, String> {
+  let current = parse_semver(current)?;
+  let latest = parse_semver(latest)?;
+  Ok(latest <= current)
+}
+
+/// Parse a version string as semver, tolerating the `v` prefix releases are
+/// conventionally tagged with (e.g. `v1.2.3`).
+fn parse_semver(version: &str) -> Result<semver::Version, String> {
+  semver::Version::parse(version.trim_start_matches('v'))
+    .map_err(|e| format!("invalid version {:?}: {}", version, e))
 }
@@ -0,0 +1,247 @@
+//! A `Backend` that discovers releases by listing objects in an S3-style
+//! bucket, so apps don't have to host their releases on GitHub.
+//!
+//! Supports plain S3, S3 dual-stack, Google Cloud Storage's S3-compatible
+//! XML API, and DigitalOcean Spaces, all of which speak the same
+//! `ListBucketResult` XML response.
+
+use regex::Regex;
+
+use super::target;
+use super::{Backend, Channel, Release};
+
+const MAX_KEYS: u32 = 1000;
+
+/// The object storage provider to list the bucket on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endpoint {
+  S3,
+  S3DualStack,
+  Gcs,
+  DigitalOceanSpaces,
+}
+
+#[derive(Clone, Debug)]
+struct ObjectEntry {
+  key: String,
+}
+
+/// A `Backend` that lists a bucket's objects to find release assets,
+/// filtering by an optional key prefix and the detected target triple.
+pub struct ObjectStorageBackend {
+  endpoint: Endpoint,
+  bucket: String,
+  region: String,
+  prefix: Option<String>,
+}
+
+impl ObjectStorageBackend {
+  /// Create a backend listing `bucket` (in `region`) on `endpoint`.
+  pub fn new(endpoint: Endpoint, bucket: &str, region: &str) -> Self {
+    Self {
+      endpoint,
+      bucket: bucket.to_owned(),
+      region: region.to_owned(),
+      prefix: None,
+    }
+  }
+
+  /// Only consider objects whose key starts with `prefix`.
+  pub fn prefix(mut self, prefix: &str) -> Self {
+    self.prefix = Some(prefix.to_owned());
+    self
+  }
+
+  fn bucket_url(&self) -> String {
+    match self.endpoint {
+      Endpoint::S3 => format!(
+        "https://{}.s3.{}.amazonaws.com",
+        self.bucket, self.region
+      ),
+      Endpoint::S3DualStack => format!(
+        "https://{}.s3.dualstack.{}.amazonaws.com",
+        self.bucket, self.region
+      ),
+      Endpoint::Gcs => format!("https://storage.googleapis.com/{}", self.bucket),
+      Endpoint::DigitalOceanSpaces => format!(
+        "https://{}.{}.digitaloceanspaces.com",
+        self.bucket, self.region
+      ),
+    }
+  }
+
+  /// List every object in the bucket, requesting up to `MAX_KEYS` objects
+  /// per page and paging with `marker` until the response is no longer
+  /// truncated. The number of pages is not limited.
+  fn list_objects(&self) -> Result<Vec<ObjectEntry>, String> {
+    let mut entries = Vec::new();
+    let mut marker: Option<String> = None;
+
+    loop {
+      let mut url = format!("{}/?max-keys={}", self.bucket_url(), MAX_KEYS);
+      if let Some(ref prefix) = self.prefix {
+        url.push_str(&format!("&prefix={}", prefix));
+      }
+      if let Some(ref marker) = marker {
+        url.push_str(&format!("&marker={}", marker));
+      }
+
+      let body = reqwest::blocking::get(&url)
+        .map_err(|e| format!("failed to list bucket: {}", e))?
+        .text()
+        .map_err(|e| format!("failed to read bucket listing: {}", e))?;
+
+      let (page, next_marker) = parse_list_bucket_result(&body)?;
+      let last_key = page.last().map(|entry| entry.key.clone());
+      entries.extend(page);
+
+      marker = match next_marker.or(last_key) {
+        Some(marker) if is_truncated(&body) => Some(marker),
+        _ => break,
+      };
+    }
+
+    Ok(entries)
+  }
+
+  /// Objects matching the configured prefix and the running target triple,
+  /// i.e. the candidate release assets.
+  fn matching_assets(&self) -> Result<Vec<ObjectEntry>, String> {
+    let target = target::current_target();
+    Ok(
+      self
+        .list_objects()?
+        .into_iter()
+        .filter(|entry| entry.key.contains(&target))
+        .collect(),
+    )
+  }
+
+  fn latest_asset(&self) -> Result<ObjectEntry, String> {
+    let mut assets = self.matching_assets()?;
+    // Sort by parsed semver, not the raw substring: lexicographic order
+    // would put "1.9.0" after "1.10.0".
+    assets.sort_by(|a, b| version_in_key(&a.key).cmp(&version_in_key(&b.key)));
+    assets
+      .pop()
+      .ok_or_else(|| format!("no release asset found for target {}", target::current_target()))
+  }
+}
+
+impl Backend for ObjectStorageBackend {
+  // `is_uptodate` is intentionally not overridden: the default on `Backend`
+  // delegates to `latest_release` (implemented below) and compares with
+  // semver, which is exactly what this backend needs.
+
+  fn update_url(&self, _version: String) -> Result<String, String> {
+    let asset = self.latest_asset()?;
+    Ok(format!("{}/{}", self.bucket_url(), asset.key))
+  }
+
+  fn latest_release(&self) -> Result<Release, String> {
+    let asset = self.latest_asset()?;
+    Ok(Release {
+      version: version_in_key(&asset.key)
+        .map(|v| v.to_string())
+        .unwrap_or_default(),
+      asset_name: asset.key.clone(),
+      download_url: format!("{}/{}", self.bucket_url(), asset.key),
+      assets: Vec::new(),
+      // Object storage buckets don't carry channel/critical metadata of
+      // their own, so every release is reported as stable and not critical.
+      channel: Channel::Stable,
+      is_critical: false,
+    })
+  }
+}
+
+/// Pull a semver-looking substring (`\d+\.\d+\.\d+`) out of an object key
+/// and parse it, so release assets sort by actual version ordering
+/// ("1.9.0" < "1.10.0") rather than byte-lexicographic order.
+fn version_in_key(key: &str) -> Option<semver::Version> {
+  let re = Regex::new(r"(\d+\.\d+\.\d+)").ok()?;
+  let captured = re.captures(key)?;
+  semver::Version::parse(&captured[1]).ok()
+}
+
+fn is_truncated(xml: &str) -> bool {
+  xml.contains("<IsTruncated>true</IsTruncated>")
+}
+
+/// Extract the `<Key>` values and next page marker out of a
+/// `ListBucketResult` XML document; `<Size>` is not read. Parsed with regexes rather than a full
+/// XML parser, matching this crate's preference for small dependencies.
+fn parse_list_bucket_result(xml: &str) -> Result<(Vec<ObjectEntry>, Option<String>), String> {
+  let key_re = Regex::new(r"<Key>([^<]+)</Key>").map_err(|e| e.to_string())?;
+  let keys = key_re
+    .captures_iter(xml)
+    .map(|caps| ObjectEntry {
+      key: caps[1].to_string(),
+    })
+    .collect();
+
+  let marker_re =
+    Regex::new(r"<NextMarker>([^<]+)</NextMarker>").map_err(|e| e.to_string())?;
+  let next_marker = marker_re.captures(xml).map(|caps| caps[1].to_string());
+
+  Ok((keys, next_marker))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn version_in_key_parses_semver_substring() {
+    assert_eq!(
+      version_in_key("releases/app-1.9.0-x86_64-unknown-linux-gnu.tar.gz"),
+      Some(semver::Version::parse("1.9.0").unwrap())
+    );
+    assert_eq!(version_in_key("releases/no-version-here.tar.gz"), None);
+  }
+
+  #[test]
+  fn version_sort_orders_by_semver_not_lexicographically() {
+    let mut assets = vec![
+      ObjectEntry {
+        key: "app-1.9.0-x86_64-unknown-linux-gnu.tar.gz".into(),
+      },
+      ObjectEntry {
+        key: "app-1.10.0-x86_64-unknown-linux-gnu.tar.gz".into(),
+      },
+      ObjectEntry {
+        key: "app-1.2.0-x86_64-unknown-linux-gnu.tar.gz".into(),
+      },
+    ];
+    assets.sort_by(|a, b| version_in_key(&a.key).cmp(&version_in_key(&b.key)));
+    assert_eq!(assets.pop().unwrap().key, "app-1.10.0-x86_64-unknown-linux-gnu.tar.gz");
+  }
+
+  #[test]
+  fn parse_list_bucket_result_extracts_keys_and_next_marker() {
+    let xml = r#"
+      <ListBucketResult>
+        <Key>releases/app-1.0.0-x86_64-unknown-linux-gnu.tar.gz</Key>
+        <Size>1234</Size>
+        <Key>releases/app-1.1.0-x86_64-unknown-linux-gnu.tar.gz</Key>
+        <Size>1235</Size>
+        <IsTruncated>true</IsTruncated>
+        <NextMarker>releases/app-1.1.0-x86_64-unknown-linux-gnu.tar.gz</NextMarker>
+      </ListBucketResult>
+    "#;
+
+    let (entries, next_marker) = parse_list_bucket_result(xml).unwrap();
+    assert_eq!(
+      entries.iter().map(|e| e.key.as_str()).collect::<Vec<_>>(),
+      vec![
+        "releases/app-1.0.0-x86_64-unknown-linux-gnu.tar.gz",
+        "releases/app-1.1.0-x86_64-unknown-linux-gnu.tar.gz",
+      ]
+    );
+    assert_eq!(
+      next_marker.as_deref(),
+      Some("releases/app-1.1.0-x86_64-unknown-linux-gnu.tar.gz")
+    );
+    assert!(is_truncated(xml));
+  }
+}
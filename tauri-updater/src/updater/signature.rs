@@ -0,0 +1,193 @@
+//! Minisign-compatible signature verification for downloaded release assets.
+//!
+//! This implements just enough of the minisign format to verify a detached
+//! signature produced by `minisign -S`: parsing the base64-encoded public
+//! key and signature files, recomputing the BLAKE2b-512 hash of the signed
+//! data, and checking both the data signature and the trusted comment
+//! signature with Ed25519.
+
+use blake2::{Blake2b512, Digest};
+use ed25519_dalek::{PublicKey as Ed25519PublicKey, Signature as Ed25519Signature, Verifier};
+
+const PUBLIC_KEY_ALG: &[u8; 2] = b"Ed";
+const SIGNATURE_ALG_PREHASH: &[u8; 2] = b"ED";
+const SIGNATURE_ALG_LEGACY: &[u8; 2] = b"Ed";
+const KEY_ID_LEN: usize = 8;
+const PUBLIC_KEY_LEN: usize = 32;
+const SIGNATURE_LEN: usize = 64;
+
+/// A parsed minisign public key (an Ed25519 key plus the key id used to
+/// match it against a signature).
+pub struct PublicKey {
+  key_id: [u8; KEY_ID_LEN],
+  inner: Ed25519PublicKey,
+}
+
+impl PublicKey {
+  /// Parse a minisign public key from its base64 representation, as found
+  /// on the second line of a `.pub`/`minisign.pub` file (the
+  /// `untrusted comment:` line, if present, is ignored by the caller).
+  pub fn decode(base64_key: &str) -> Result<Self, String> {
+    let bytes = base64::decode(base64_key.trim())
+      .map_err(|e| format!("invalid base64 public key: {}", e))?;
+    if bytes.len() != 2 + KEY_ID_LEN + PUBLIC_KEY_LEN {
+      return Err("public key has an unexpected length".into());
+    }
+    if &bytes[0..2] != PUBLIC_KEY_ALG {
+      return Err("unsupported public key algorithm".into());
+    }
+    let mut key_id = [0u8; KEY_ID_LEN];
+    key_id.copy_from_slice(&bytes[2..2 + KEY_ID_LEN]);
+    let inner = Ed25519PublicKey::from_bytes(&bytes[2 + KEY_ID_LEN..])
+      .map_err(|e| format!("invalid Ed25519 public key: {}", e))?;
+    Ok(Self { key_id, inner })
+  }
+}
+
+/// A parsed minisign signature file: the signature over the (possibly
+/// pre-hashed) data, the trusted comment it covers, and the signature over
+/// that comment.
+struct Signature {
+  key_id: [u8; KEY_ID_LEN],
+  prehashed: bool,
+  data_signature: Ed25519Signature,
+  trusted_comment: String,
+  comment_signature: Ed25519Signature,
+}
+
+impl Signature {
+  fn decode(contents: &str) -> Result<Self, String> {
+    let mut lines = contents.lines();
+    let _untrusted_comment = lines
+      .next()
+      .ok_or_else(|| "empty signature file".to_string())?;
+    let sig_line = lines
+      .next()
+      .ok_or_else(|| "signature file is missing its signature line".to_string())?;
+    let trusted_comment_line = lines
+      .next()
+      .ok_or_else(|| "signature file is missing a trusted comment".to_string())?;
+    let global_sig_line = lines
+      .next()
+      .ok_or_else(|| "signature file is missing the global signature".to_string())?;
+
+    let sig_bytes = base64::decode(sig_line.trim())
+      .map_err(|e| format!("invalid base64 signature: {}", e))?;
+    if sig_bytes.len() != 2 + KEY_ID_LEN + SIGNATURE_LEN {
+      return Err("signature has an unexpected length".into());
+    }
+    let alg = [sig_bytes[0], sig_bytes[1]];
+    let prehashed = if &alg == SIGNATURE_ALG_PREHASH {
+      true
+    } else if &alg == SIGNATURE_ALG_LEGACY {
+      false
+    } else {
+      return Err("unsupported signature algorithm".into());
+    };
+    let mut key_id = [0u8; KEY_ID_LEN];
+    key_id.copy_from_slice(&sig_bytes[2..2 + KEY_ID_LEN]);
+    let data_signature = Ed25519Signature::from_bytes(&sig_bytes[2 + KEY_ID_LEN..])
+      .map_err(|e| format!("invalid data signature: {}", e))?;
+
+    let trusted_comment = trusted_comment_line
+      .strip_prefix("trusted comment: ")
+      .ok_or_else(|| "malformed trusted comment line".to_string())?
+      .to_string();
+
+    let global_sig_bytes = base64::decode(global_sig_line.trim())
+      .map_err(|e| format!("invalid base64 global signature: {}", e))?;
+    if global_sig_bytes.len() != SIGNATURE_LEN {
+      return Err("global signature has an unexpected length".into());
+    }
+    let comment_signature = Ed25519Signature::from_bytes(&global_sig_bytes)
+      .map_err(|e| format!("invalid global signature: {}", e))?;
+
+    Ok(Self {
+      key_id,
+      prehashed,
+      data_signature,
+      trusted_comment,
+      comment_signature,
+    })
+  }
+}
+
+/// Verify `data` against a minisign `signature` (the `.minisig` file
+/// contents) using `public_key`.
+///
+/// Returns an error describing the failure if the key ids don't match, the
+/// signature over the data is invalid, or the signature over the trusted
+/// comment is invalid.
+pub fn verify(public_key: &PublicKey, signature: &str, data: &[u8]) -> Result<(), String> {
+  let signature = Signature::decode(signature)?;
+  if signature.key_id != public_key.key_id {
+    return Err("signature was produced by a different key".into());
+  }
+
+  let signed_bytes: Vec<u8> = if signature.prehashed {
+    let mut hasher = Blake2b512::new();
+    hasher.update(data);
+    hasher.finalize().to_vec()
+  } else {
+    data.to_vec()
+  };
+
+  public_key
+    .inner
+    .verify(&signed_bytes, &signature.data_signature)
+    .map_err(|_| "signature verification failed".to_string())?;
+
+  let mut comment_payload = Vec::with_capacity(SIGNATURE_LEN + signature.trusted_comment.len());
+  comment_payload.extend_from_slice(&signature.data_signature.to_bytes());
+  comment_payload.extend_from_slice(signature.trusted_comment.as_bytes());
+  public_key
+    .inner
+    .verify(&comment_payload, &signature.comment_signature)
+    .map_err(|_| "trusted comment verification failed".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Generated with a throwaway Ed25519 keypair: key id 00..07, signing the
+  // message below in minisign's legacy (non-prehashed) mode.
+  const PUBKEY_B64: &str = "RWQAAQIDBAUGBxf+G5yTR6HCicc3AdA/Bv/Ht9Bi+fQhIasT/IySPJCD";
+  const MESSAGE: &[u8] = b"hello minisign\n";
+  const MINISIG: &str = "untrusted comment: minisign test signature\n\
+RWQAAQIDBAUGB4wQOJqppYBcdxruIYCNJuyTs8thUY8kDH6ZnaCoLnf+VNa2Aeqs6DuG2XjUNNCx4F52tZeEcYmr6CqNcUdCMA0=\n\
+trusted comment: timestamp:1700000000\tfile:release.tar.gz\thashed\n\
+gkRSfR+9oddG9CZUcpB/EAgRW6cHpi5188di6uo9JAyVVqKOvLRu/7EIZqjWk6muVjFl8Rp1Fqb8YHjOvvDvDg==\n";
+
+  #[test]
+  fn decodes_a_valid_public_key() {
+    let key = PublicKey::decode(PUBKEY_B64).unwrap();
+    assert_eq!(key.key_id, [0, 1, 2, 3, 4, 5, 6, 7]);
+  }
+
+  #[test]
+  fn rejects_a_public_key_with_the_wrong_length() {
+    assert!(PublicKey::decode("RWQAAQIDBAUGBw==").is_err());
+  }
+
+  #[test]
+  fn verifies_a_matching_signature() {
+    let key = PublicKey::decode(PUBKEY_B64).unwrap();
+    verify(&key, MINISIG, MESSAGE).unwrap();
+  }
+
+  #[test]
+  fn rejects_tampered_data() {
+    let key = PublicKey::decode(PUBKEY_B64).unwrap();
+    assert!(verify(&key, MINISIG, b"hello minisign, tampered\n").is_err());
+  }
+
+  #[test]
+  fn rejects_a_signature_from_a_different_key() {
+    // Same length/shape as PUBKEY_B64 but with a different key id, so the
+    // id check should fail before any cryptographic verification runs.
+    let other_key_b64 = "RWQICQoLDA0ODxe6ZVgTx66WY9p9gLVEvgzRuW++qEOFnpo2dEZhvVDI";
+    let key = PublicKey::decode(other_key_b64).unwrap();
+    assert!(verify(&key, MINISIG, MESSAGE).is_err());
+  }
+}
@@ -0,0 +1,29 @@
+//! Detects the running platform's target triple so a `Backend` can expose
+//! one release with several platform-specific assets and the updater can
+//! pick the one that matches.
+
+/// Return the Rust-style target triple for the platform the updater is
+/// currently running on (e.g. `x86_64-unknown-linux-gnu`,
+/// `aarch64-apple-darwin`, `x86_64-pc-windows-msvc`).
+///
+/// Falls back to `<arch>-unknown-<os>` for combinations we don't
+/// explicitly recognize rather than failing outright.
+pub fn current_target() -> String {
+  match (std::env::consts::OS, std::env::consts::ARCH) {
+    ("linux", "x86_64") => "x86_64-unknown-linux-gnu".to_string(),
+    ("linux", "aarch64") => "aarch64-unknown-linux-gnu".to_string(),
+    ("linux", "x86") => "i686-unknown-linux-gnu".to_string(),
+    ("macos", "x86_64") => "x86_64-apple-darwin".to_string(),
+    ("macos", "aarch64") => "aarch64-apple-darwin".to_string(),
+    ("windows", "x86_64") => "x86_64-pc-windows-msvc".to_string(),
+    ("windows", "x86") => "i686-pc-windows-msvc".to_string(),
+    ("windows", "aarch64") => "aarch64-pc-windows-msvc".to_string(),
+    (os, arch) => format!("{}-unknown-{}", arch, os),
+  }
+}
+
+/// Return the `(os, arch)` pair used to match a release asset's
+/// `platform_match`, using the same names as `std::env::consts::OS`/`ARCH`.
+pub fn current_os_arch() -> (&'static str, &'static str) {
+  (std::env::consts::OS, std::env::consts::ARCH)
+}
@@ -14,12 +14,20 @@ struct Reply {
 
 struct DummyBackend {}
 impl tauri_updater::updater::Backend for DummyBackend {
-  fn is_uptodate(&self, version: String) -> Result<bool, String> {
-    Ok(false)
-  }
   fn update_url(&self, version: String) -> Result<String, String> {
     Ok("https://github.com/jaemk/self_update/releases/download/v9.9.10/self_update-v9.9.10-x86_64-unknown-linux-gnu.tar.gz".to_string())
   }
+
+  fn latest_release(&self) -> Result<tauri_updater::updater::Release, String> {
+    Ok(tauri_updater::updater::Release {
+      version: "9.9.10".to_string(),
+      asset_name: "self_update-v9.9.10-x86_64-unknown-linux-gnu.tar.gz".to_string(),
+      download_url: "https://github.com/jaemk/self_update/releases/download/v9.9.10/self_update-v9.9.10-x86_64-unknown-linux-gnu.tar.gz".to_string(),
+      assets: Vec::new(),
+      channel: tauri_updater::updater::Channel::Stable,
+      is_critical: false,
+    })
+  }
 }
 
 fn test_download() {